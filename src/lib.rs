@@ -1,23 +1,84 @@
 #![allow(dead_code)]
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt::Display;
 use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Add;
 
-/// A weighted directional graph.
-pub struct WeightedDigraph<K: Eq + Hash, V, E> {
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Marks whether a [`GenericGraph`]'s edges are directed or undirected. Not
+/// meant to be implemented outside this crate.
+pub trait EdgeType {}
+
+/// Marker for graphs whose edges point from one vertex to another.
+pub enum Directed {}
+
+/// Marker for graphs whose edges have no inherent orientation.
+pub enum Undirected {}
+
+impl EdgeType for Directed {}
+impl EdgeType for Undirected {}
+
+/// The edge weight used by the unweighted aliases ([`Digraph`], [`Graph`]).
+///
+/// The unit type `()` would be the obvious choice here, but `to_dot` needs a
+/// single `E: Display` bound shared by both weighted and unweighted graphs so
+/// every [`GenericGraph`] gets the same method, and that doesn't work out
+/// with `()`: `()` is a foreign type, so we can't write `impl Display for
+/// ()` ourselves (orphan rule), and special-casing it alongside a generic
+/// `impl<E: Display> ...` — whether via a blanket trait impl or a second
+/// concrete inherent `impl GenericGraph<K, V, (), Ty>` — is rejected by
+/// rustc (E0119/E0592) because `()` could gain a `Display` impl upstream in
+/// the future, which would make the two impls overlap. `Unweighted` sidesteps
+/// this entirely by giving us a type we own that can have its own `Display`
+/// (rendering as an empty label) without any risk of that future conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Unweighted;
+
+impl Display for Unweighted {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+/// A graph over vertices keyed by `K` with values `V`, edges weighted by `E`,
+/// and directionality determined by the `Ty` marker ([`Directed`] or
+/// [`Undirected`]).
+///
+/// Prefer the type aliases [`Digraph`], [`Graph`], [`WeightedDigraph`], and
+/// [`WeightedGraph`] over naming this type directly.
+pub struct GenericGraph<K: Eq + Hash, V, E, Ty: EdgeType> {
     vertex_map: HashMap<K, V>,
     repr: GraphRepr<K, E>,
+    _marker: PhantomData<Ty>,
 }
 
-impl<K: Eq + Hash, V, E> Default for WeightedDigraph<K, V, E> {
+/// An unweighted directional graph.
+pub type Digraph<K, V> = GenericGraph<K, V, Unweighted, Directed>;
+
+/// A weighted directional graph.
+pub type WeightedDigraph<K, V, E> = GenericGraph<K, V, E, Directed>;
+
+/// An unweighted undirectional graph.
+pub type Graph<K, V> = GenericGraph<K, V, Unweighted, Undirected>;
+
+/// A weighted undirectional graph.
+pub type WeightedGraph<K, V, E> = GenericGraph<K, V, E, Undirected>;
+
+impl<K: Eq + Hash, V, E, Ty: EdgeType> Default for GenericGraph<K, V, E, Ty> {
     fn default() -> Self {
         Self {
             vertex_map: HashMap::new(),
             repr: GraphRepr::default(),
+            _marker: PhantomData,
         }
     }
 }
 
-impl<K: Eq + Hash, V, E> WeightedDigraph<K, V, E> {
+impl<K: Eq + Hash, V, E, Ty: EdgeType> GenericGraph<K, V, E, Ty> {
     pub fn new() -> Self {
         Self::default()
     }
@@ -26,54 +87,532 @@ impl<K: Eq + Hash, V, E> WeightedDigraph<K, V, E> {
         self.vertex_map.get(key)
     }
 
-    pub fn connect(&mut self, u: &K, v: K, weight: E) {
-        self.repr.connect(u, v, weight);
-    }
-
     pub fn get_edge(&self, u: &K, v: &K) -> Option<&E> {
         self.repr.get_edge(u, v)
     }
 }
 
-impl<K: Clone + Eq + Hash, V, E> WeightedDigraph<K, V, E> {
+impl<K: Clone + Eq + Hash, V, E, Ty: EdgeType> GenericGraph<K, V, E, Ty> {
     pub fn insert(&mut self, key: K, value: V) {
         self.vertex_map.insert(key.clone(), value);
         self.repr.insert(key);
     }
 }
 
-/// An unweighted directional graph.
-pub struct Digraph<K: Eq + Hash, V> {
-    vertex_map: HashMap<K, V>,
-    repr: GraphRepr<K, ()>,
+impl<K: Clone + Eq + Hash, V, E, Ty: EdgeType> GenericGraph<K, V, E, Ty> {
+    /// Builds an empty graph pinned to `repr`, opting out of the automatic
+    /// density-driven switching performed by [`Self::optimize`].
+    pub fn with_representation(repr: Repr) -> Self {
+        Self {
+            vertex_map: HashMap::new(),
+            repr: GraphRepr::with_representation(repr),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Migrates the internal representation to whichever backend suits the
+    /// graph's current density, unless the backend was pinned via
+    /// [`Self::with_representation`]. `insert`, `connect`, `remove_vertex`
+    /// and `remove_edge` already call this automatically.
+    pub fn optimize(&mut self) {
+        self.repr.optimize();
+    }
+
+    /// Removes `key` and every edge incident to it, returning its value if
+    /// it was present.
+    pub fn remove_vertex(&mut self, key: &K) -> Option<V> {
+        let value = self.vertex_map.remove(key)?;
+        self.repr.remove_vertex(key);
+        Some(value)
+    }
 }
 
-/// A weighted undirectional graph.
-pub struct WeightedGraph<K: Eq + Hash, V, E> {
-    vertex_map: HashMap<K, V>,
-    repr: GraphRepr<K, E>,
+impl<K: Clone + Eq + Hash, V, E> GenericGraph<K, V, E, Directed> {
+    pub fn connect(&mut self, u: &K, v: K, weight: E) {
+        self.repr.connect(u, v, weight);
+    }
+
+    pub fn remove_edge(&mut self, u: &K, v: &K) -> Option<E> {
+        self.repr.remove_edge(u, v)
+    }
 }
 
-/// An unweighted undirectional graph.
-pub struct Graph<K: Eq + Hash, V> {
-    vertex_map: HashMap<K, V>,
-    repr: GraphRepr<K, ()>,
+impl<K: Clone + Eq + Hash, V, E: Clone> GenericGraph<K, V, E, Undirected> {
+    /// Connects `u` and `v` with `weight` in both directions, since edges in
+    /// an undirected graph have no inherent orientation.
+    pub fn connect(&mut self, u: &K, v: K, weight: E) {
+        self.repr.connect(u, v.clone(), weight.clone());
+        self.repr.connect(&v, u.clone(), weight);
+    }
+
+    /// Removes the edge in both directions, since undirected edges have no
+    /// inherent orientation. Returns the weight stored on the `u -> v` side.
+    pub fn remove_edge(&mut self, u: &K, v: &K) -> Option<E> {
+        let removed = self.repr.remove_edge(u, v);
+        self.repr.remove_edge(v, u);
+        removed
+    }
 }
 
-// TODO: This should probably be a trait?
+impl<K, V, E, Ty> GenericGraph<K, V, E, Ty>
+where
+    K: Clone + Eq + Hash + Ord,
+    E: Clone + Ord + Add<Output = E> + Default,
+    Ty: EdgeType,
+{
+    /// Finds the cheapest path from `src` to `dst` using Dijkstra's algorithm,
+    /// returning the path (inclusive of both endpoints) alongside its total cost.
+    ///
+    /// Returns `None` if `dst` is unreachable from `src`.
+    pub fn shortest_path(&self, src: &K, dst: &K) -> Option<(Vec<K>, E)> {
+        dijkstra(&self.repr, src, dst)
+    }
 
-enum GraphRepr<K: Eq + Hash, E> {
-    AdjacencyList(AdjacencyList<K, E>),
-    AdjacencyMatrix(AdjacencyMatrix<K, E>),
+    /// Like [`shortest_path`](Self::shortest_path), but guides the search with
+    /// a heuristic `h` estimating the remaining cost to `dst`. `h` must be
+    /// admissible (never overestimate the true remaining cost) or the result
+    /// may not be optimal.
+    pub fn astar(&self, src: &K, dst: &K, h: impl Fn(&K) -> E) -> Option<(Vec<K>, E)> {
+        astar(&self.repr, src, dst, h)
+    }
 }
 
-impl<K: Eq + Hash, E> Default for GraphRepr<K, E> {
-    fn default() -> Self {
-        Self::AdjacencyList(AdjacencyList::new())
+impl<K: Eq + Hash + Display, V: Display, E: Display> GenericGraph<K, V, E, Directed> {
+    /// Renders the graph as a Graphviz DOT `digraph`, with vertex labels
+    /// taken from their values and edges labeled with their weights.
+    pub fn to_dot(&self) -> String {
+        to_dot_directed(&self.vertex_map, self.repr.edges(), |weight| {
+            format!(" [label=\"{weight}\"]")
+        })
     }
 }
 
-impl<K: Eq + Hash, E> GraphRepr<K, E> {
+impl<K: Clone + Eq + Hash + Display, V: Display, E: Display> GenericGraph<K, V, E, Undirected> {
+    /// See [`GenericGraph::<K, V, E, Directed>::to_dot`]. Since edges are
+    /// undirected, each pair is only emitted once, using `--` rather than `->`.
+    pub fn to_dot(&self) -> String {
+        to_dot_undirected(&self.vertex_map, self.repr.edges(), |weight| {
+            format!(" [label=\"{weight}\"]")
+        })
+    }
+}
+
+/// The wire format for a serialized graph: a vertex list plus an edge list,
+/// independent of whichever [`Repr`] happens to back the live graph.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct GraphDataRef<'a, K, V, E> {
+    vertices: Vec<(&'a K, &'a V)>,
+    edges: Vec<(&'a K, &'a K, &'a E)>,
+}
+
+/// Owned counterpart of [`GraphDataRef`], used to rebuild a graph from its
+/// serialized vertex/edge lists on deserialization.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct GraphData<K, V, E> {
+    vertices: Vec<(K, V)>,
+    edges: Vec<(K, K, E)>,
+}
+
+#[cfg(feature = "serde")]
+impl<K: Eq + Hash + Serialize, V: Serialize, E: Serialize> Serialize
+    for GenericGraph<K, V, E, Directed>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphDataRef {
+            vertices: self.vertex_map.iter().collect(),
+            edges: self.repr.edges(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K: Clone + Eq + Hash + Deserialize<'de>, V: Deserialize<'de>, E: Deserialize<'de>>
+    Deserialize<'de> for GenericGraph<K, V, E, Directed>
+{
+    /// Rebuilds the graph via [`GenericGraph::insert`]/[`GenericGraph::connect`]
+    /// so its invariants hold regardless of which backend ends up live,
+    /// rather than trusting a serialized `GraphRepr` directly.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GraphData::<K, V, E>::deserialize(deserializer)?;
+        let mut graph = Self::new();
+        for (key, value) in data.vertices {
+            graph.insert(key, value);
+        }
+        for (u, v, weight) in data.edges {
+            graph.connect(&u, v, weight);
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K: Clone + Eq + Hash + Serialize, V: Serialize, E: Serialize> Serialize
+    for GenericGraph<K, V, E, Undirected>
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphDataRef {
+            vertices: self.vertex_map.iter().collect(),
+            edges: dedupe_undirected_edges(self.repr.edges()),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<
+        'de,
+        K: Clone + Eq + Hash + Deserialize<'de>,
+        V: Deserialize<'de>,
+        E: Clone + Deserialize<'de>,
+    > Deserialize<'de> for GenericGraph<K, V, E, Undirected>
+{
+    /// See [`GenericGraph::<K, V, E, Directed>::deserialize`]. Each
+    /// serialized edge is connected once; [`GenericGraph::connect`] takes
+    /// care of writing both orderings.
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GraphData::<K, V, E>::deserialize(deserializer)?;
+        let mut graph = Self::new();
+        for (key, value) in data.vertices {
+            graph.insert(key, value);
+        }
+        for (u, v, weight) in data.edges {
+            graph.connect(&u, v, weight);
+        }
+        Ok(graph)
+    }
+}
+
+impl<K: Clone + Eq + Hash, V, E, Ty: EdgeType> GenericGraph<K, V, E, Ty> {
+    /// Traverses the graph breadth-first starting at `start`, yielding each
+    /// reachable key (including `start`) exactly once.
+    pub fn bfs(&self, start: &K) -> Bfs<'_, K, E> {
+        Bfs::new(&self.repr, start)
+    }
+
+    /// Traverses the graph depth-first starting at `start`, yielding each
+    /// reachable key (including `start`) exactly once.
+    pub fn dfs(&self, start: &K) -> Dfs<'_, K, E> {
+        Dfs::new(&self.repr, start)
+    }
+}
+
+impl<K: Clone + Eq + Hash, V, E> GenericGraph<K, V, E, Directed> {
+    /// Orders all vertices such that every edge `u -> v` has `u` before `v`.
+    ///
+    /// Returns [`CycleError`] if the graph is not a DAG.
+    pub fn topological_sort(&self) -> Result<Vec<K>, CycleError> {
+        topological_sort(&self.repr, self.vertex_map.keys().cloned())
+    }
+}
+
+fn dijkstra<K, E>(repr: &GraphRepr<K, E>, src: &K, dst: &K) -> Option<(Vec<K>, E)>
+where
+    K: Clone + Eq + Hash + Ord,
+    E: Clone + Ord + Add<Output = E> + Default,
+{
+    if src == dst {
+        return Some((vec![src.clone()], E::default()));
+    }
+
+    let mut dist: HashMap<K, E> = HashMap::new();
+    let mut prev: HashMap<K, K> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    dist.insert(src.clone(), E::default());
+    heap.push(Reverse((E::default(), src.clone())));
+
+    while let Some(Reverse((d, u))) = heap.pop() {
+        if dist.get(&u).is_some_and(|best| d > *best) {
+            // Stale entry: a cheaper path to `u` was already found.
+            continue;
+        }
+        if u == *dst {
+            break;
+        }
+        for (v, weight) in repr.neighbors(&u) {
+            let next = d.clone() + weight.clone();
+            if dist.get(v).is_none_or(|cur| next < *cur) {
+                dist.insert(v.clone(), next.clone());
+                prev.insert(v.clone(), u.clone());
+                heap.push(Reverse((next, v.clone())));
+            }
+        }
+    }
+
+    let cost = dist.get(dst)?.clone();
+    Some((reconstruct_path(&prev, src, dst), cost))
+}
+
+fn astar<K, E>(
+    repr: &GraphRepr<K, E>,
+    src: &K,
+    dst: &K,
+    h: impl Fn(&K) -> E,
+) -> Option<(Vec<K>, E)>
+where
+    K: Clone + Eq + Hash + Ord,
+    E: Clone + Ord + Add<Output = E> + Default,
+{
+    if src == dst {
+        return Some((vec![src.clone()], E::default()));
+    }
+
+    let mut g_score: HashMap<K, E> = HashMap::new();
+    let mut prev: HashMap<K, K> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    g_score.insert(src.clone(), E::default());
+    heap.push(Reverse((h(src), src.clone())));
+
+    while let Some(Reverse((priority, u))) = heap.pop() {
+        // Stale entry: `u` was reached more cheaply since this was pushed.
+        // No closed set, so a node can be reopened and relaxed again if a
+        // cheaper path surfaces later; that's needed for correctness with
+        // any admissible (not necessarily consistent) heuristic.
+        if priority > g_score[&u].clone() + h(&u) {
+            continue;
+        }
+        if u == *dst {
+            break;
+        }
+        let g_u = g_score[&u].clone();
+        for (v, weight) in repr.neighbors(&u) {
+            let tentative = g_u.clone() + weight.clone();
+            if g_score.get(v).is_none_or(|cur| tentative < *cur) {
+                g_score.insert(v.clone(), tentative.clone());
+                prev.insert(v.clone(), u.clone());
+                heap.push(Reverse((tentative + h(v), v.clone())));
+            }
+        }
+    }
+
+    let cost = g_score.get(dst)?.clone();
+    Some((reconstruct_path(&prev, src, dst), cost))
+}
+
+fn reconstruct_path<K: Clone + Eq + Hash>(prev: &HashMap<K, K>, src: &K, dst: &K) -> Vec<K> {
+    let mut path = vec![dst.clone()];
+    let mut cur = dst;
+    while cur != src {
+        let Some(p) = prev.get(cur) else { break };
+        path.push(p.clone());
+        cur = p;
+    }
+    path.reverse();
+    path
+}
+
+fn to_dot_directed<K: Eq + Hash + Display, V: Display, E>(
+    vertex_map: &HashMap<K, V>,
+    edges: Vec<(&K, &K, &E)>,
+    edge_label: impl Fn(&E) -> String,
+) -> String {
+    let mut out = String::from("digraph {\n");
+    for (key, value) in vertex_map {
+        out.push_str(&format!("    \"{key}\" [label=\"{value}\"];\n"));
+    }
+    for (u, v, weight) in edges {
+        out.push_str(&format!("    \"{u}\" -> \"{v}\"{};\n", edge_label(weight)));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn to_dot_undirected<K: Clone + Eq + Hash + Display, V: Display, E>(
+    vertex_map: &HashMap<K, V>,
+    edges: Vec<(&K, &K, &E)>,
+    edge_label: impl Fn(&E) -> String,
+) -> String {
+    let mut out = String::from("graph {\n");
+    for (key, value) in vertex_map {
+        out.push_str(&format!("    \"{key}\" [label=\"{value}\"];\n"));
+    }
+    for (u, v, weight) in dedupe_undirected_edges(edges) {
+        out.push_str(&format!("    \"{u}\" -- \"{v}\"{};\n", edge_label(weight)));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Undirected edges are stored in both orderings (`u, v` and `v, u`), so any
+/// consumer that wants to see each edge once — DOT export, serialization —
+/// needs to collapse those pairs back down to a single canonical ordering.
+fn dedupe_undirected_edges<'a, K: Clone + Eq + Hash, E>(
+    edges: Vec<(&'a K, &'a K, &'a E)>,
+) -> Vec<(&'a K, &'a K, &'a E)> {
+    let mut seen = HashSet::new();
+    let mut deduped = Vec::new();
+    for (u, v, weight) in edges {
+        if seen.contains(&(v.clone(), u.clone())) {
+            continue;
+        }
+        seen.insert((u.clone(), v.clone()));
+        deduped.push((u, v, weight));
+    }
+    deduped
+}
+
+/// The classic three-color scheme used to track traversal state: a vertex is
+/// `White` until discovered, `Gray` while it is on the frontier/stack, and
+/// `Black` once it has been visited. The `Bfs` and `Dfs` iterators mark a
+/// vertex `Black` when they yield it; `topological_sort` marks it `Black`
+/// only once everything reachable from it has been finished.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    White,
+    Gray,
+    Black,
+}
+
+/// A breadth-first traversal over a graph's reachable keys, returned by
+/// [`WeightedDigraph::bfs`] and friends.
+pub struct Bfs<'a, K: Eq + Hash, E> {
+    repr: &'a GraphRepr<K, E>,
+    queue: VecDeque<K>,
+    color: HashMap<K, Color>,
+}
+
+impl<'a, K: Clone + Eq + Hash, E> Bfs<'a, K, E> {
+    fn new(repr: &'a GraphRepr<K, E>, start: &K) -> Self {
+        let mut color = HashMap::new();
+        color.insert(start.clone(), Color::Gray);
+        Self {
+            repr,
+            queue: VecDeque::from([start.clone()]),
+            color,
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, E> Iterator for Bfs<'_, K, E> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let node = self.queue.pop_front()?;
+        for (neighbor, _) in self.repr.neighbors(&node) {
+            if self.color.get(neighbor).is_none_or(|c| *c == Color::White) {
+                self.color.insert(neighbor.clone(), Color::Gray);
+                self.queue.push_back(neighbor.clone());
+            }
+        }
+        self.color.insert(node.clone(), Color::Black);
+        Some(node)
+    }
+}
+
+/// A depth-first traversal over a graph's reachable keys, returned by
+/// [`WeightedDigraph::dfs`] and friends.
+pub struct Dfs<'a, K: Eq + Hash, E> {
+    repr: &'a GraphRepr<K, E>,
+    stack: Vec<K>,
+    color: HashMap<K, Color>,
+}
+
+impl<'a, K: Clone + Eq + Hash, E> Dfs<'a, K, E> {
+    fn new(repr: &'a GraphRepr<K, E>, start: &K) -> Self {
+        let mut color = HashMap::new();
+        color.insert(start.clone(), Color::Gray);
+        Self {
+            repr,
+            stack: vec![start.clone()],
+            color,
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, E> Iterator for Dfs<'_, K, E> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        while let Some(node) = self.stack.pop() {
+            if self.color.get(&node) == Some(&Color::Black) {
+                continue;
+            }
+            self.color.insert(node.clone(), Color::Black);
+            for (neighbor, _) in self.repr.neighbors(&node) {
+                if self.color.get(neighbor) != Some(&Color::Black) {
+                    self.color.insert(neighbor.clone(), Color::Gray);
+                    self.stack.push(neighbor.clone());
+                }
+            }
+            return Some(node);
+        }
+        None
+    }
+}
+
+/// Returned by `topological_sort` when the graph contains a cycle, since no
+/// valid ordering exists in that case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleError;
+
+impl std::fmt::Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "graph contains a cycle")
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+fn topological_sort<K: Clone + Eq + Hash, E>(
+    repr: &GraphRepr<K, E>,
+    vertices: impl Iterator<Item = K>,
+) -> Result<Vec<K>, CycleError> {
+    let mut color: HashMap<K, Color> = HashMap::new();
+    let mut order = Vec::new();
+    for vertex in vertices {
+        if color.get(&vertex).is_none_or(|c| *c != Color::Black) {
+            visit_for_topological_sort(repr, &vertex, &mut color, &mut order)?;
+        }
+    }
+    order.reverse();
+    Ok(order)
+}
+
+fn visit_for_topological_sort<K: Clone + Eq + Hash, E>(
+    repr: &GraphRepr<K, E>,
+    node: &K,
+    color: &mut HashMap<K, Color>,
+    order: &mut Vec<K>,
+) -> Result<(), CycleError> {
+    color.insert(node.clone(), Color::Gray);
+    for (neighbor, _) in repr.neighbors(node) {
+        match color.get(neighbor) {
+            Some(Color::Gray) => return Err(CycleError),
+            Some(Color::Black) => continue,
+            _ => visit_for_topological_sort(repr, neighbor, color, order)?,
+        }
+    }
+    color.insert(node.clone(), Color::Black);
+    order.push(node.clone());
+    Ok(())
+}
+
+/// Which backing representation a graph uses internally. Pass to
+/// [`GenericGraph::with_representation`] to pin a backend and opt out of
+/// the automatic density-driven switching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repr {
+    AdjacencyList,
+    AdjacencyMatrix,
+}
+
+/// A graph becomes "dense" once it has more than a quarter as many edges as
+/// the square of its vertex count, at which point an adjacency matrix's O(1)
+/// lookups pay for its O(n^2) memory.
+fn is_dense(n: usize, m: usize) -> bool {
+    n > 0 && m > n * n / 4
+}
+
+enum GraphReprData<K: Eq + Hash, E> {
+    AdjacencyList(AdjacencyList<K, E>),
+    AdjacencyMatrix(AdjacencyMatrix<K, E>),
+}
+
+impl<K: Eq + Hash, E> GraphReprData<K, E> {
     fn insert(&mut self, key: K) {
         match self {
             Self::AdjacencyList(list) => list.insert(key),
@@ -94,6 +633,182 @@ impl<K: Eq + Hash, E> GraphRepr<K, E> {
             Self::AdjacencyMatrix(matrix) => matrix.get_edge(u, v),
         }
     }
+
+    fn neighbors(&self, u: &K) -> Vec<(&K, &E)> {
+        match self {
+            Self::AdjacencyList(list) => list.neighbors(u),
+            Self::AdjacencyMatrix(matrix) => matrix.neighbors(u),
+        }
+    }
+
+    fn edges(&self) -> Vec<(&K, &K, &E)> {
+        match self {
+            Self::AdjacencyList(list) => list.edges(),
+            Self::AdjacencyMatrix(matrix) => matrix.edges(),
+        }
+    }
+
+    fn remove_vertex(&mut self, key: &K) {
+        match self {
+            Self::AdjacencyList(list) => list.remove_vertex(key),
+            Self::AdjacencyMatrix(matrix) => matrix.remove_vertex(key),
+        }
+    }
+
+    fn remove_edge(&mut self, u: &K, v: &K) -> Option<E> {
+        match self {
+            Self::AdjacencyList(list) => list.remove_edge(u, v),
+            Self::AdjacencyMatrix(matrix) => matrix.remove_edge(u, v),
+        }
+    }
+
+    fn vertex_count(&self) -> usize {
+        match self {
+            Self::AdjacencyList(list) => list.vertex_count(),
+            Self::AdjacencyMatrix(matrix) => matrix.vertex_count(),
+        }
+    }
+
+    fn edge_count(&self) -> usize {
+        match self {
+            Self::AdjacencyList(list) => list.edge_count(),
+            Self::AdjacencyMatrix(matrix) => matrix.edge_count(),
+        }
+    }
+}
+
+/// Wraps [`GraphReprData`] with an optional pinned [`Repr`]: when unpinned,
+/// `optimize` migrates the live data to whichever backend suits the graph's
+/// current density.
+struct GraphRepr<K: Eq + Hash, E> {
+    data: GraphReprData<K, E>,
+    pinned: Option<Repr>,
+}
+
+impl<K: Eq + Hash, E> Default for GraphRepr<K, E> {
+    fn default() -> Self {
+        Self {
+            data: GraphReprData::AdjacencyList(AdjacencyList::new()),
+            pinned: None,
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, E> GraphRepr<K, E> {
+    fn with_representation(repr: Repr) -> Self {
+        let data = match repr {
+            Repr::AdjacencyList => GraphReprData::AdjacencyList(AdjacencyList::new()),
+            Repr::AdjacencyMatrix => GraphReprData::AdjacencyMatrix(AdjacencyMatrix::new()),
+        };
+        Self {
+            data,
+            pinned: Some(repr),
+        }
+    }
+
+    fn insert(&mut self, key: K) {
+        self.data.insert(key);
+        self.optimize();
+    }
+
+    fn connect(&mut self, u: &K, v: K, weight: E) {
+        self.data.connect(u, v, weight);
+        self.optimize();
+    }
+
+    fn remove_vertex(&mut self, key: &K) {
+        self.data.remove_vertex(key);
+        self.optimize();
+    }
+
+    fn remove_edge(&mut self, u: &K, v: &K) -> Option<E> {
+        let removed = self.data.remove_edge(u, v);
+        self.optimize();
+        removed
+    }
+
+    /// Re-checks density and migrates representation if it no longer fits,
+    /// unless the backend has been pinned via [`Self::with_representation`].
+    fn optimize(&mut self) {
+        if self.pinned.is_some() {
+            return;
+        }
+        let dense = is_dense(self.data.vertex_count(), self.data.edge_count());
+        match &self.data {
+            GraphReprData::AdjacencyList(_) if dense => {
+                let GraphReprData::AdjacencyList(list) = std::mem::replace(
+                    &mut self.data,
+                    GraphReprData::AdjacencyMatrix(AdjacencyMatrix::new()),
+                ) else {
+                    unreachable!()
+                };
+                self.data = GraphReprData::AdjacencyMatrix(reindex_into_matrix(list));
+            }
+            GraphReprData::AdjacencyMatrix(_) if !dense => {
+                let GraphReprData::AdjacencyMatrix(matrix) = std::mem::replace(
+                    &mut self.data,
+                    GraphReprData::AdjacencyList(AdjacencyList::new()),
+                ) else {
+                    unreachable!()
+                };
+                self.data = GraphReprData::AdjacencyList(collect_into_list(matrix));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<K: Eq + Hash, E> GraphRepr<K, E> {
+    fn get_edge(&self, u: &K, v: &K) -> Option<&E> {
+        self.data.get_edge(u, v)
+    }
+
+    fn neighbors(&self, u: &K) -> Vec<(&K, &E)> {
+        self.data.neighbors(u)
+    }
+
+    fn edges(&self) -> Vec<(&K, &K, &E)> {
+        self.data.edges()
+    }
+}
+
+/// Moves every vertex and edge out of `list` into a freshly built matrix,
+/// preserving all edge data between vertices. An edge whose target was never
+/// inserted as a vertex has no matrix slot and is dropped.
+fn reindex_into_matrix<K: Clone + Eq + Hash, E>(list: AdjacencyList<K, E>) -> AdjacencyMatrix<K, E> {
+    let mut matrix = AdjacencyMatrix::new();
+    for key in list.list.keys() {
+        matrix.insert(key.clone());
+    }
+    for (u, edges) in list.list {
+        for (v, weight) in edges {
+            matrix.connect(&u, v, weight);
+        }
+    }
+    matrix
+}
+
+/// Moves every vertex and edge out of `matrix` into a freshly built list,
+/// preserving all edge data.
+fn collect_into_list<K: Clone + Eq + Hash, E>(matrix: AdjacencyMatrix<K, E>) -> AdjacencyList<K, E> {
+    let mut list = AdjacencyList::new();
+    let mut idx_to_key: Vec<Option<K>> = vec![None; matrix.matrix.len()];
+    for (key, &idx) in &matrix.idx_map {
+        idx_to_key[idx] = Some(key.clone());
+        list.insert(key.clone());
+    }
+    for (u_idx, row) in matrix.matrix.into_iter().enumerate() {
+        let Some(u) = &idx_to_key[u_idx] else {
+            continue;
+        };
+        for (v_idx, weight) in row.into_iter().enumerate() {
+            let (Some(weight), Some(v)) = (weight, idx_to_key[v_idx].clone()) else {
+                continue;
+            };
+            list.connect(u, v, weight);
+        }
+    }
+    list
 }
 
 struct AdjacencyList<K: Eq + Hash, E> {
@@ -121,6 +836,42 @@ impl<K: Eq + Hash, E> AdjacencyList<K, E> {
     fn get_edge(&self, u: &K, v: &K) -> Option<&E> {
         self.list.get(u)?.get(v)
     }
+
+    fn neighbors(&self, u: &K) -> Vec<(&K, &E)> {
+        self.list
+            .get(u)
+            .into_iter()
+            .flat_map(|edges| edges.iter())
+            .collect()
+    }
+
+    fn edges(&self) -> Vec<(&K, &K, &E)> {
+        self.list
+            .iter()
+            .flat_map(|(u, edges)| edges.iter().map(move |(v, weight)| (u, v, weight)))
+            .collect()
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.list.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.list.values().map(|edges| edges.len()).sum()
+    }
+
+    /// Removes `key` and purges every edge pointing at it from the other
+    /// vertices' edge maps.
+    fn remove_vertex(&mut self, key: &K) {
+        self.list.remove(key);
+        for edges in self.list.values_mut() {
+            edges.remove(key);
+        }
+    }
+
+    fn remove_edge(&mut self, u: &K, v: &K) -> Option<E> {
+        self.list.get_mut(u)?.remove(v)
+    }
 }
 
 struct AdjacencyMatrix<K: Hash, E> {
@@ -160,6 +911,63 @@ impl<K: Eq + Hash, E> AdjacencyMatrix<K, E> {
     fn idxs(&self, u: &K, v: &K) -> Option<(usize, usize)> {
         Some((*self.idx_map.get(u)?, *self.idx_map.get(v)?))
     }
+
+    fn neighbors(&self, u: &K) -> Vec<(&K, &E)> {
+        let Some(&u_idx) = self.idx_map.get(u) else {
+            return Vec::new();
+        };
+        self.idx_map
+            .iter()
+            .filter_map(|(k, &idx)| self.matrix[u_idx][idx].as_ref().map(|weight| (k, weight)))
+            .collect()
+    }
+
+    fn edges(&self) -> Vec<(&K, &K, &E)> {
+        let mut edges = Vec::new();
+        for (u, &u_idx) in &self.idx_map {
+            for (v, &v_idx) in &self.idx_map {
+                if let Some(weight) = &self.matrix[u_idx][v_idx] {
+                    edges.push((u, v, weight));
+                }
+            }
+        }
+        edges
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.idx_map.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.matrix
+            .iter()
+            .flatten()
+            .filter(|weight| weight.is_some())
+            .count()
+    }
+
+    /// Removes `key`'s row and column, then shifts every index past it down
+    /// by one so `idx_map` stays densely packed (a full compaction rebuild,
+    /// rather than tombstoning the freed slot).
+    fn remove_vertex(&mut self, key: &K) {
+        let Some(removed_idx) = self.idx_map.remove(key) else {
+            return;
+        };
+        self.matrix.remove(removed_idx);
+        for row in self.matrix.iter_mut() {
+            row.remove(removed_idx);
+        }
+        for idx in self.idx_map.values_mut() {
+            if *idx > removed_idx {
+                *idx -= 1;
+            }
+        }
+    }
+
+    fn remove_edge(&mut self, u: &K, v: &K) -> Option<E> {
+        let (u_idx, v_idx) = self.idxs(u, v)?;
+        self.matrix[u_idx][v_idx].take()
+    }
 }
 
 #[cfg(test)]
@@ -185,4 +993,296 @@ mod test {
         assert_eq!(graph.get_edge(&1, &2), Some(&10_000));
         assert_eq!(graph.get_edge(&2, &1), None);
     }
+
+    #[test]
+    fn shortest_path_digraph() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=4 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, 1);
+        graph.connect(&2, 3, 1);
+        graph.connect(&1, 3, 5);
+        graph.connect(&3, 4, 1);
+        assert_eq!(graph.shortest_path(&1, &4), Some((vec![1, 2, 3, 4], 3)));
+        assert_eq!(graph.shortest_path(&1, &1), Some((vec![1], 0)));
+        assert_eq!(graph.shortest_path(&4, &1), None);
+    }
+
+    #[test]
+    fn astar_digraph_matches_dijkstra() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=4 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, 1);
+        graph.connect(&2, 3, 1);
+        graph.connect(&1, 3, 5);
+        graph.connect(&3, 4, 1);
+        assert_eq!(graph.astar(&1, &4, |_| 0), Some((vec![1, 2, 3, 4], 3)));
+    }
+
+    #[test]
+    fn shortest_path_undirected_graph() {
+        let mut graph = WeightedGraph::new();
+        for key in 1..=3 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, 2);
+        graph.connect(&2, 3, 3);
+        assert_eq!(graph.shortest_path(&1, &3), Some((vec![1, 2, 3], 5)));
+        assert_eq!(graph.shortest_path(&3, &1), Some((vec![3, 2, 1], 5)));
+    }
+
+    #[test]
+    fn to_dot_digraph() {
+        let mut graph = WeightedDigraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, 5);
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("\"1\" [label=\"a\"];"));
+        assert!(dot.contains("\"1\" -> \"2\" [label=\"5\"];"));
+    }
+
+    #[test]
+    fn to_dot_undirected_graph_dedupes_edges() {
+        let mut graph = WeightedGraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, 5);
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph {\n"));
+        assert_eq!(dot.matches("--").count(), 1);
+    }
+
+    #[test]
+    fn bfs_visits_each_reachable_key_once() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=4 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, ());
+        graph.connect(&1, 3, ());
+        graph.connect(&2, 4, ());
+        let mut visited: Vec<_> = graph.bfs(&1).collect();
+        visited.sort();
+        assert_eq!(visited, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dfs_visits_each_reachable_key_once() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=4 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, ());
+        graph.connect(&1, 3, ());
+        graph.connect(&2, 4, ());
+        let mut visited: Vec<_> = graph.dfs(&1).collect();
+        visited.sort();
+        assert_eq!(visited, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn topological_sort_orders_dependencies_first() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=3 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, ());
+        graph.connect(&2, 3, ());
+        assert_eq!(graph.topological_sort(), Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn topological_sort_detects_cycle() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=3 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, ());
+        graph.connect(&2, 3, ());
+        graph.connect(&3, 1, ());
+        assert_eq!(graph.topological_sort(), Err(CycleError));
+    }
+
+    #[test]
+    fn unweighted_digraph_connects_and_traverses() {
+        let mut graph = Digraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, Unweighted);
+        assert_eq!(graph.get_edge(&1, &2), Some(&Unweighted));
+        assert_eq!(graph.get_edge(&2, &1), None);
+        assert_eq!(graph.bfs(&1).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(graph.to_dot().contains("\"1\" -> \"2\""));
+    }
+
+    #[test]
+    fn unweighted_graph_connects_symmetrically() {
+        let mut graph = Graph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, Unweighted);
+        assert_eq!(graph.get_edge(&1, &2), Some(&Unweighted));
+        assert_eq!(graph.get_edge(&2, &1), Some(&Unweighted));
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"1\" -- \"2\"") || dot.contains("\"2\" -- \"1\""));
+    }
+
+    #[test]
+    fn graph_switches_to_matrix_once_dense() {
+        let mut graph = WeightedDigraph::new();
+        for key in 1..=4 {
+            graph.insert(key, ());
+        }
+        assert!(matches!(graph.repr.data, GraphReprData::AdjacencyList(_)));
+        // 4 vertices: dense once edges exceed 4*4/4 = 4.
+        for (u, v) in [(1, 2), (1, 3), (1, 4), (2, 3), (2, 4)] {
+            graph.connect(&u, v, ());
+        }
+        assert!(matches!(graph.repr.data, GraphReprData::AdjacencyMatrix(_)));
+        assert_eq!(graph.get_edge(&1, &2), Some(&()));
+        assert_eq!(graph.get_edge(&2, &4), Some(&()));
+        assert_eq!(graph.get_edge(&3, &1), None);
+    }
+
+    #[test]
+    fn with_representation_pins_backend() {
+        let mut graph = WeightedDigraph::<_, _, ()>::with_representation(Repr::AdjacencyMatrix);
+        for key in 1..=3 {
+            graph.insert(key, ());
+        }
+        graph.connect(&1, 2, ());
+        assert!(matches!(graph.repr.data, GraphReprData::AdjacencyMatrix(_)));
+        assert_eq!(graph.get_edge(&1, &2), Some(&()));
+    }
+
+    #[test]
+    fn remove_vertex_purges_incoming_edges_from_list_backend() {
+        let mut graph = WeightedDigraph::with_representation(Repr::AdjacencyList);
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.insert(3, "c");
+        graph.connect(&1, 2, "x");
+        graph.connect(&3, 2, "y");
+        assert_eq!(graph.remove_vertex(&2), Some("b"));
+        assert_eq!(graph.get(&2), None);
+        assert_eq!(graph.get_edge(&1, &2), None);
+        assert_eq!(graph.get_edge(&3, &2), None);
+    }
+
+    #[test]
+    fn remove_vertex_reindexes_matrix_backend() {
+        let mut graph = WeightedDigraph::with_representation(Repr::AdjacencyMatrix);
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.insert(3, "c");
+        graph.connect(&1, 2, "x");
+        graph.connect(&1, 3, "y");
+        graph.connect(&2, 3, "z");
+        assert_eq!(graph.remove_vertex(&2), Some("b"));
+        assert_eq!(graph.get_edge(&1, &3), Some(&"y"));
+        assert_eq!(graph.get_edge(&1, &2), None);
+        assert_eq!(graph.get_edge(&2, &3), None);
+    }
+
+    #[test]
+    fn remove_edge_only_clears_requested_direction() {
+        let mut graph = WeightedDigraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, "x");
+        graph.connect(&2, 1, "y");
+        assert_eq!(graph.remove_edge(&1, &2), Some("x"));
+        assert_eq!(graph.get_edge(&1, &2), None);
+        assert_eq!(graph.get_edge(&2, &1), Some(&"y"));
+    }
+
+    #[test]
+    fn remove_edge_clears_both_directions_in_undirected_graph() {
+        let mut graph = WeightedGraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, "x");
+        assert_eq!(graph.remove_edge(&1, &2), Some("x"));
+        assert_eq!(graph.get_edge(&1, &2), None);
+        assert_eq!(graph.get_edge(&2, &1), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips_directed_graph() {
+        let mut graph = WeightedDigraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, 5);
+        let json = serde_json::to_string(&graph).unwrap();
+        let restored: WeightedDigraph<i32, &str, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get(&1), Some(&"a"));
+        assert_eq!(restored.get_edge(&1, &2), Some(&5));
+        assert_eq!(restored.get_edge(&2, &1), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_preserves_undirected_symmetry() {
+        let mut graph = WeightedGraph::new();
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.connect(&1, 2, 5);
+        let json = serde_json::to_string(&graph).unwrap();
+        // Exactly one edge should be serialized, not both symmetric orderings.
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["edges"].as_array().unwrap().len(), 1);
+        let restored: WeightedGraph<i32, &str, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get_edge(&1, &2), Some(&5));
+        assert_eq!(restored.get_edge(&2, &1), Some(&5));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips_matrix_backed_graph_through_list_format() {
+        let mut graph = WeightedDigraph::with_representation(Repr::AdjacencyMatrix);
+        graph.insert(1, "a");
+        graph.insert(2, "b");
+        graph.insert(3, "c");
+        graph.connect(&1, 2, 5);
+        graph.connect(&2, 3, 6);
+        let json = serde_json::to_string(&graph).unwrap();
+        let restored: WeightedDigraph<i32, &str, i32> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(restored.repr.data, GraphReprData::AdjacencyList(_)));
+        assert_eq!(restored.get_edge(&1, &2), Some(&5));
+        assert_eq!(restored.get_edge(&2, &3), Some(&6));
+    }
+
+    #[test]
+    fn reindex_into_matrix_preserves_edge_data() {
+        let mut list = AdjacencyList::new();
+        list.insert(1);
+        list.insert(2);
+        list.insert(3);
+        list.connect(&1, 2, "a");
+        list.connect(&2, 3, "b");
+        let matrix = reindex_into_matrix(list);
+        assert_eq!(matrix.get_edge(&1, &2), Some(&"a"));
+        assert_eq!(matrix.get_edge(&2, &3), Some(&"b"));
+        assert_eq!(matrix.get_edge(&3, &1), None);
+    }
+
+    #[test]
+    fn collect_into_list_preserves_edge_data() {
+        let mut matrix = AdjacencyMatrix::new();
+        matrix.insert(1);
+        matrix.insert(2);
+        matrix.insert(3);
+        matrix.connect(&1, 2, "a");
+        matrix.connect(&2, 3, "b");
+        let list = collect_into_list(matrix);
+        assert_eq!(list.get_edge(&1, &2), Some(&"a"));
+        assert_eq!(list.get_edge(&2, &3), Some(&"b"));
+        assert_eq!(list.get_edge(&3, &1), None);
+    }
 }